@@ -18,24 +18,74 @@
 //! "^-.*" red
 //! "^+.*" green
 //! ```
+//!
+//! A style list entry may also contain whitespace-separated `N:desc`/`name:desc` tokens to style
+//! a pattern's capture groups individually, falling back to any plain color list in the same
+//! entry (or no style) for the rest of the match:
+//!
+//! ```
+//! [kv]
+//! "(\w+)=(\w+)" 1:green 2:yellow
+//! "(?P<k>\w+):(?P<v>.*)" k:cyan v:white
+//! ```
+//!
+//! Instead of a quoted match pattern, an entry may start with `pair` or `toggle` to style a run
+//! of text delimited by markers rather than a single match, potentially spanning several lines:
+//!
+//! ```
+//! [markdown]
+//! pair "```" "```" green
+//! toggle "\*" bold
+//! ```
 
-use crate::{Description, MatchStyle};
+use crate::{MatchStyle, SpanKind, SpanStyle, parse_style_spec};
 use regex::Regex;
 use std::path::PathBuf;
 
-/// Find a list of [`MatchStyle`] in the `config` string under the `style` section.
-fn find_style(config: &str, style: &str) -> Result<Vec<MatchStyle>, String> {
+/// Find the [`MatchStyle`]s and [`SpanStyle`]s in the `config` string under the `style` section.
+fn find_style(config: &str, style: &str) -> Result<(Vec<MatchStyle>, Vec<SpanStyle>), String> {
     let section = Regex::new(r"^\s*\[(\w+)\]\s*$").expect("creating section pattern");
     let match_style = Regex::new(r#"^\s*"(.*)"\s*(.*)$"#).expect("creating match style pattern");
-    let mut result = Vec::new();
+    let pair_style =
+        Regex::new(r#"^\s*pair\s+"(.*?)"\s+"(.*?)"\s*(.*)$"#).expect("creating pair style pattern");
+    let toggle_style =
+        Regex::new(r#"^\s*toggle\s+"(.*)"\s*(.*)$"#).expect("creating toggle style pattern");
+    let mut matches = Vec::new();
+    let mut spans = Vec::new();
     let mut append = false;
 
     for line in config.lines() {
         if append {
+            if let Some(captures) = pair_style.captures(line) {
+                let open = Regex::new(&captures[1]).map_err(|err| err.to_string())?;
+                let close = Regex::new(&captures[2]).map_err(|err| err.to_string())?;
+                let (style, _) = parse_style_spec(&captures[3])?;
+                spans.push(SpanStyle {
+                    kind: SpanKind::Pair { open, close },
+                    style,
+                });
+                continue;
+            }
+
+            if let Some(captures) = toggle_style.captures(line) {
+                let marker = Regex::new(&captures[1]).map_err(|err| err.to_string())?;
+                let (style, _) = parse_style_spec(&captures[2])?;
+                spans.push(SpanStyle {
+                    kind: SpanKind::Toggle(marker),
+                    style,
+                });
+                continue;
+            }
+
             if let Some(captures) = match_style.captures(line) {
                 let pattern = Regex::new(&captures[1]).map_err(|err| err.to_string())?;
-                let style = Description(&captures[2]).try_into()?;
-                result.push(MatchStyle { pattern, style });
+                let (style, group_styles) = parse_style_spec(&captures[2])?;
+                matches.push(MatchStyle {
+                    pattern,
+                    style,
+                    template: None,
+                    group_styles,
+                });
                 continue;
             }
         }
@@ -45,15 +95,15 @@ fn find_style(config: &str, style: &str) -> Result<Vec<MatchStyle>, String> {
         }
     }
 
-    Ok(result)
+    Ok((matches, spans))
 }
 
-pub fn read_style_from_config(style: &str) -> Result<Vec<MatchStyle>, String> {
+pub fn read_style_from_config(style: &str) -> Result<(Vec<MatchStyle>, Vec<SpanStyle>), String> {
     let mut path = match (std::env::var("XDG_CONFIG_HOME"), std::env::var("HOME")) {
         (Ok(config_home), _) => PathBuf::from(config_home),
         (_, Ok(home)) => PathBuf::from(home).join(".config"),
         (_, _) => {
-            return Ok(Vec::new());
+            return Ok((Vec::new(), Vec::new()));
         }
     };
 
@@ -64,7 +114,7 @@ pub fn read_style_from_config(style: &str) -> Result<Vec<MatchStyle>, String> {
         let config = std::fs::read_to_string(&path).map_err(|err| err.to_string())?;
         find_style(&config, style)
     } else {
-        Ok(Vec::new())
+        Ok((Vec::new(), Vec::new()))
     }
 }
 
@@ -78,19 +128,35 @@ mod tests {
 [qux]
 "world" yellow,underline
 "foo" red
+"#;
+
+    const SPAN_CONFIG: &str = r#"[markdown]
+pair "```" "```" green
+toggle "\*" bold
 "#;
 
     #[test]
     fn find_none() {
-        assert!(find_style(CONFIG, "bar").unwrap().is_empty());
+        let (matches, spans) = find_style(CONFIG, "bar").unwrap();
+        assert!(matches.is_empty());
+        assert!(spans.is_empty());
     }
 
     #[test]
     fn find_all_styles() {
-        let styles = find_style(CONFIG, "foo").unwrap();
-        assert_eq!(styles.len(), 1);
+        let (matches, _) = find_style(CONFIG, "foo").unwrap();
+        assert_eq!(matches.len(), 1);
+
+        let (matches, _) = find_style(CONFIG, "qux").unwrap();
+        assert_eq!(matches.len(), 2);
+    }
 
-        let styles = find_style(CONFIG, "qux").unwrap();
-        assert_eq!(styles.len(), 2);
+    #[test]
+    fn find_span_styles() {
+        let (matches, spans) = find_style(SPAN_CONFIG, "markdown").unwrap();
+        assert!(matches.is_empty());
+        assert_eq!(spans.len(), 2);
+        assert!(matches!(spans[0].kind, SpanKind::Pair { .. }));
+        assert!(matches!(spans[1].kind, SpanKind::Toggle(_)));
     }
 }
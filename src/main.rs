@@ -1,5 +1,8 @@
 use owo_colors::{OwoColorize, Style};
 use regex::Regex;
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::VecDeque;
 use std::process::ExitCode;
 
 mod config;
@@ -8,8 +11,68 @@ mod config;
 struct MatchStyle {
     /// Regular expression pattern whose match will be styled with `style`.
     pattern: Regex,
-    /// Text style for the match.
+    /// Text style for the match, also used for any part of the match not covered by
+    /// `group_styles`.
     style: Style,
+    /// Replacement template expanding capture group references such as `$1` or `${name}`. If
+    /// set, the match is rewritten rather than printed verbatim before `style` is applied.
+    template: Option<String>,
+    /// Per-capture-group style overrides, applied over `style` for the span of each referenced
+    /// group. Ignored when `template` is set, since the match is rewritten as a whole then.
+    group_styles: Vec<(GroupRef, Style)>,
+}
+
+/// Reference to a capture group within a pattern, either by 1-based index or by name.
+enum GroupRef {
+    /// Capture group referenced by its 1-based index.
+    Index(usize),
+    /// Capture group referenced by its name.
+    Name(String),
+}
+
+impl GroupRef {
+    /// Resolve this reference against `captures`, returning `None` if the group did not
+    /// participate in the match.
+    fn resolve<'input>(&self, captures: &regex::Captures<'input>) -> Option<regex::Match<'input>> {
+        match self {
+            GroupRef::Index(index) => captures.get(*index),
+            GroupRef::Name(name) => captures.name(name),
+        }
+    }
+}
+
+/// Style applied to a run of text delimited by markers rather than a single regex match.
+struct SpanStyle {
+    /// How the delimiting marker(s) are recognized.
+    kind: SpanKind,
+    /// Text style applied to the whole delimited run, markers included.
+    style: Style,
+}
+
+/// The two ways a [`SpanStyle`] can delimit the text it covers.
+enum SpanKind {
+    /// Style flips on, then off, each time `marker` is matched.
+    Toggle(Regex),
+    /// Style applies from an `open` match through the next `close` match, even across lines.
+    Pair {
+        /// Pattern that starts the styled run.
+        open: Regex,
+        /// Pattern that ends the styled run.
+        close: Regex,
+    },
+}
+
+/// A contiguous piece of a line, either covered by an active [`SpanStyle`] or free to be matched
+/// against the regular [`MatchStyle`]s.
+enum SpanSegment<'style> {
+    /// `[start, end)` is styled with `style`, markers included.
+    Span {
+        start: usize,
+        end: usize,
+        style: &'style Style,
+    },
+    /// `[start, end)` is not covered by any span and still needs regular matching.
+    Free { start: usize, end: usize },
 }
 
 /// String description of a style.
@@ -17,17 +80,22 @@ struct MatchStyle {
 /// Description consists of a comma-separated list of colors and effects.
 struct Description<'input>(&'input str);
 
-/// List of match styles.
-struct MatchStyles<'style>(&'style [MatchStyle]);
+/// List of match styles, plus a [`regex::RegexSet`] over the same patterns so `find_match` can
+/// cheaply narrow down which patterns are worth running `captures` for on a given piece of text.
+struct MatchStyles<'style> {
+    styles: &'style [MatchStyle],
+    set: regex::RegexSet,
+}
 
 /// Region inside a line which is either unmatched and printed verbatim or matched and printed with
 /// a style applied.
 enum Region<'input, 'style> {
     /// Text region has no match pattern and to be printed verbatim.
     Unmatched { text: &'input str },
-    /// Text region matched and is to be styled with `style`.
+    /// Text region matched and is to be styled with `style`. `text` is either borrowed from the
+    /// input or, if the match was rewritten through a replacement template, an owned string.
     Matched {
-        text: &'input str,
+        text: Cow<'input, str>,
         style: &'style Style,
     },
 }
@@ -38,14 +106,24 @@ struct Regions<'input, 'style> {
     text: &'input str,
     /// Available match expressions and styles.
     styles: &'style MatchStyles<'style>,
-    /// Previous match.
-    previous: Option<(&'input str, &'style Style)>,
+    /// Regions held back to be returned on subsequent calls to `next`.
+    pending: VecDeque<Region<'input, 'style>>,
 }
 
 /// Parser to match regions over lines.
+///
+/// Besides the regular [`MatchStyle`]s, a [`Parser`] also carries `spans` and, for each of them,
+/// whether it is currently open. Because [`SpanKind::Pair`] and [`SpanKind::Toggle`] runs can
+/// stretch across multiple lines, that "currently open" state has to survive from one call to
+/// [`Parser::regions`] to the next, so it lives in `active` rather than in the per-line
+/// [`Regions`] iterator.
 struct Parser<'style> {
     /// Available match expressions and styles.
     styles: MatchStyles<'style>,
+    /// Available delimiter-based span styles.
+    spans: &'style [SpanStyle],
+    /// Whether each of `spans` is currently open, carried across lines.
+    active: Vec<Cell<bool>>,
 }
 
 /// Parsed command line options.
@@ -55,41 +133,175 @@ struct Opts {
     help: bool,
     /// Loaded match style.
     styles: Vec<MatchStyle>,
+    /// Loaded delimiter-based span styles.
+    spans: Vec<SpanStyle>,
 }
 
 impl<'style> Parser<'style> {
-    /// Create new [`Parser`] given the `styles` match patterns.
-    fn new(styles: &'style [MatchStyle]) -> Self {
+    /// Create new [`Parser`] given the `styles` match patterns and `spans`.
+    fn new(styles: &'style [MatchStyle], spans: &'style [SpanStyle]) -> Self {
         Self {
             styles: MatchStyles::new(styles),
+            spans,
+            active: spans.iter().map(|_| Cell::new(false)).collect(),
         }
     }
 
-    /// Return [`Regions`] iterator over matched and umatched regions found in `text`.
-    fn regions<'input>(&'style self, text: &'input str) -> Regions<'input, 'style> {
-        Regions {
-            text,
-            styles: &self.styles,
-            previous: None,
+    /// Split `text` into the parts covered by a currently active or newly opened span and the
+    /// parts still free for regular matching, updating `active` for any span that opens, closes
+    /// or stays open past the end of `text`.
+    fn span_segments(&'style self, text: &str) -> Vec<SpanSegment<'style>> {
+        let mut pending_start: Vec<Option<usize>> = self
+            .active
+            .iter()
+            .map(|active| active.get().then_some(0))
+            .collect();
+        let mut segments = Vec::new();
+        let mut pos = 0;
+
+        while pos < text.len() {
+            if let Some(index) = (0..self.spans.len()).find(|&index| self.active[index].get()) {
+                let span = &self.spans[index];
+                let start = pending_start[index].expect("active span always has a start");
+
+                let closing = match &span.kind {
+                    SpanKind::Toggle(marker) => marker.find(&text[pos..]),
+                    SpanKind::Pair { close, .. } => close.find(&text[pos..]),
+                };
+
+                match closing {
+                    Some(m) => {
+                        let end = pos + m.end();
+                        segments.push(SpanSegment::Span {
+                            start,
+                            end,
+                            style: &span.style,
+                        });
+                        self.active[index].set(false);
+                        pending_start[index] = None;
+                        pos = end;
+                    }
+                    None => {
+                        segments.push(SpanSegment::Span {
+                            start,
+                            end: text.len(),
+                            style: &span.style,
+                        });
+                        pos = text.len();
+                    }
+                }
+
+                continue;
+            }
+
+            let earliest = self
+                .spans
+                .iter()
+                .enumerate()
+                .filter_map(|(index, span)| {
+                    let marker = match &span.kind {
+                        SpanKind::Toggle(marker) => marker,
+                        SpanKind::Pair { open, .. } => open,
+                    };
+                    marker
+                        .find(&text[pos..])
+                        .map(|m| (pos + m.start(), pos + m.end(), index))
+                })
+                .min_by_key(|(start, _, _)| *start);
+
+            match earliest {
+                None => {
+                    segments.push(SpanSegment::Free {
+                        start: pos,
+                        end: text.len(),
+                    });
+                    break;
+                }
+                Some((start, marker_end, index)) => {
+                    if start > pos {
+                        segments.push(SpanSegment::Free {
+                            start: pos,
+                            end: start,
+                        });
+                    }
+
+                    self.active[index].set(true);
+                    pending_start[index] = Some(start);
+                    pos = marker_end;
+                }
+            }
         }
+
+        segments
+    }
+
+    /// Return an iterator over matched and unmatched regions found in `text`, resuming any span
+    /// left open by a previous line and possibly leaving one open for the next.
+    fn regions<'input>(
+        &'style self,
+        text: &'input str,
+    ) -> std::vec::IntoIter<Region<'input, 'style>> {
+        let mut result = Vec::new();
+
+        for segment in self.span_segments(text) {
+            match segment {
+                SpanSegment::Span { start, end, style } if start < end => {
+                    result.push(Region::Matched {
+                        text: Cow::Borrowed(&text[start..end]),
+                        style,
+                    });
+                }
+                SpanSegment::Span { .. } => {}
+                SpanSegment::Free { start, end } => {
+                    result.extend(Regions {
+                        text: &text[start..end],
+                        styles: &self.styles,
+                        pending: VecDeque::new(),
+                    });
+                }
+            }
+        }
+
+        result.into_iter()
     }
 }
 
 impl<'style> MatchStyles<'style> {
-    /// Create a new [`MatchStyles`] object.
+    /// Create a new [`MatchStyles`] object, compiling a [`regex::RegexSet`] over `styles`' patterns
+    /// once up front so `find_match` does not have to run every pattern's `captures` on every call.
     fn new(styles: &'style [MatchStyle]) -> Self {
-        Self(styles)
+        let set = regex::RegexSet::new(styles.iter().map(|style| style.pattern.as_str()))
+            .expect("patterns were already validated individually when compiled");
+        Self { styles, set }
     }
 
-    /// Find a match in `text` and the corresponding style or `None`.
+    /// Find a match in `text` and the corresponding [`MatchStyle`] or `None`.
     fn find_match<'input>(
         &self,
         text: &'input str,
-    ) -> Option<(regex::Match<'input>, &'style Style)> {
-        self.0
+    ) -> Option<(regex::Captures<'input>, &'style MatchStyle)> {
+        let candidates = self.set.matches(text);
+
+        self.styles
             .iter()
-            .filter_map(|style| style.pattern.find(text).map(|m| (m, &style.style)))
-            .min_by(|x, y| x.0.start().cmp(&y.0.start()))
+            .enumerate()
+            .filter(|(index, _)| candidates.matched(*index))
+            .filter_map(|(_, style)| {
+                style
+                    .pattern
+                    .captures(text)
+                    .map(|captures| (captures, style))
+            })
+            .min_by(|x, y| {
+                x.0.get(0)
+                    .expect("captures always have a whole match")
+                    .start()
+                    .cmp(
+                        &y.0.get(0)
+                            .expect("captures always have a whole match")
+                            .start(),
+                    )
+            })
     }
 }
 
@@ -97,8 +309,8 @@ impl<'input, 'style> Iterator for Regions<'input, 'style> {
     type Item = Region<'input, 'style>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some((text, style)) = self.previous.take() {
-            return Some(Region::Matched { text, style });
+        if let Some(region) = self.pending.pop_front() {
+            return Some(region);
         }
 
         if self.text.is_empty() {
@@ -109,29 +321,110 @@ impl<'input, 'style> Iterator for Regions<'input, 'style> {
             None => {
                 let text = self.text;
                 self.text = &self.text[self.text.len()..];
-                Some(Region::Unmatched { text })
+                return Some(Region::Unmatched { text });
             }
-            Some((m, style)) => {
+            Some((captures, match_style)) => {
+                let m = captures.get(0).expect("captures always have a whole match");
                 let start = m.start();
+                let end = m.end();
 
                 if start > 0 {
-                    // The match is not at the beginning, so store it, return unmatched text now
-                    // and the match in the next iteration.
-                    let text = &self.text[..start];
-                    self.text = &self.text[m.end()..];
-                    self.previous = Some((m.as_str(), style));
-
-                    Some(Region::Unmatched { text })
-                } else {
-                    let end = m.end();
-                    let text = &self.text[..end];
-                    self.text = &self.text[end..];
-
-                    Some(Region::Matched { text, style })
+                    self.pending.push_back(Region::Unmatched {
+                        text: &self.text[..start],
+                    });
                 }
+
+                self.pending
+                    .extend(match_regions(self.text, &captures, match_style, start, end));
+
+                self.text = &self.text[end..];
             }
         }
+
+        self.pending.pop_front()
+    }
+}
+
+/// Build the region(s) covering a single match `[start, end)` of `text`, either a single region
+/// for the whole match (the common case, and always the case for a replacement template) or,
+/// when `match_style` carries per-group overrides, one region per configured group interleaved
+/// with `match_style.style`-styled regions for the uncovered gaps.
+fn match_regions<'input, 'style>(
+    text: &'input str,
+    captures: &regex::Captures<'input>,
+    match_style: &'style MatchStyle,
+    start: usize,
+    end: usize,
+) -> Vec<Region<'input, 'style>> {
+    if let Some(template) = &match_style.template {
+        let mut expanded = String::new();
+        captures.expand(template, &mut expanded);
+
+        // Mirror ripgrep's replacer: an empty expansion has nothing to style, so fall back to
+        // an empty unmatched region and leave the surrounding text untouched.
+        return vec![if expanded.is_empty() {
+            Region::Unmatched { text: "" }
+        } else {
+            Region::Matched {
+                text: Cow::Owned(expanded),
+                style: &match_style.style,
+            }
+        }];
+    }
+
+    if match_style.group_styles.is_empty() {
+        return vec![Region::Matched {
+            text: Cow::Borrowed(&text[start..end]),
+            style: &match_style.style,
+        }];
+    }
+
+    let mut spans: Vec<(usize, usize, &'style Style)> = match_style
+        .group_styles
+        .iter()
+        .filter_map(|(group_ref, style)| {
+            group_ref
+                .resolve(captures)
+                .map(|m| (m.start(), m.end(), style))
+        })
+        .collect();
+
+    spans.sort_by_key(|(start, _, _)| *start);
+
+    let mut regions = Vec::new();
+    let mut cursor = start;
+
+    for (group_start, group_end, style) in spans {
+        // Clamp to handle groups configured out of order or overlapping with an earlier one.
+        let group_start = group_start.max(cursor).min(end);
+        let group_end = group_end.min(end);
+
+        if group_start >= group_end {
+            continue;
+        }
+
+        if group_start > cursor {
+            regions.push(Region::Matched {
+                text: Cow::Borrowed(&text[cursor..group_start]),
+                style: &match_style.style,
+            });
+        }
+
+        regions.push(Region::Matched {
+            text: Cow::Borrowed(&text[group_start..group_end]),
+            style,
+        });
+        cursor = group_end;
+    }
+
+    if cursor < end {
+        regions.push(Region::Matched {
+            text: Cow::Borrowed(&text[cursor..end]),
+            style: &match_style.style,
+        });
     }
+
+    regions
 }
 
 impl Opts {
@@ -150,8 +443,9 @@ impl Opts {
                         return Err("expected style after --style/-s".into());
                     }
                     Some(name) => {
-                        opts.styles
-                            .append(&mut config::read_style_from_config(&name)?);
+                        let (matches, spans) = config::read_style_from_config(&name)?;
+                        opts.styles.extend(matches);
+                        opts.spans.extend(spans);
                     }
                 }
             }
@@ -160,56 +454,282 @@ impl Opts {
                 match (args.next(), args.next()) {
                     (Some(pattern), Some(description)) => {
                         let pattern = Regex::new(&pattern).map_err(|err| err.to_string())?;
-                        let style = Description(&description).try_into()?;
-                        opts.styles.push(MatchStyle { pattern, style });
+                        let (style, group_styles) = parse_style_spec(&description)?;
+                        opts.styles.push(MatchStyle {
+                            pattern,
+                            style,
+                            template: None,
+                            group_styles,
+                        });
                     }
                     _ => return Err("expected pattern and style after --match/-m".into()),
                 }
             }
+
+            if arg == "--replace" || arg == "-r" {
+                match (args.next(), args.next(), args.next()) {
+                    (Some(pattern), Some(template), Some(description)) => {
+                        let pattern = Regex::new(&pattern).map_err(|err| err.to_string())?;
+                        let (style, group_styles) = parse_style_spec(&description)?;
+                        opts.styles.push(MatchStyle {
+                            pattern,
+                            style,
+                            template: Some(template),
+                            group_styles,
+                        });
+                    }
+                    _ => {
+                        return Err(
+                            "expected pattern, template and style after --replace/-r".into()
+                        );
+                    }
+                }
+            }
+
+            if arg == "--toggle" || arg == "-t" {
+                match (args.next(), args.next()) {
+                    (Some(pattern), Some(description)) => {
+                        let marker = Regex::new(&pattern).map_err(|err| err.to_string())?;
+                        let (style, _) = parse_style_spec(&description)?;
+                        opts.spans.push(SpanStyle {
+                            kind: SpanKind::Toggle(marker),
+                            style,
+                        });
+                    }
+                    _ => return Err("expected pattern and style after --toggle/-t".into()),
+                }
+            }
+
+            if arg == "--pair" || arg == "-p" {
+                match (args.next(), args.next(), args.next()) {
+                    (Some(open), Some(close), Some(description)) => {
+                        let open = Regex::new(&open).map_err(|err| err.to_string())?;
+                        let close = Regex::new(&close).map_err(|err| err.to_string())?;
+                        let (style, _) = parse_style_spec(&description)?;
+                        opts.spans.push(SpanStyle {
+                            kind: SpanKind::Pair { open, close },
+                            style,
+                        });
+                    }
+                    _ => {
+                        return Err(
+                            "expected open pattern, close pattern and style after --pair/-p".into(),
+                        );
+                    }
+                }
+            }
         }
 
         Ok(opts)
     }
 }
 
+/// Split a style description into its comma-separated parts, ignoring commas nested inside
+/// `rgb(...)` calls.
+fn split_style_parts(input: &str) -> impl Iterator<Item = &str> {
+    let mut depth = 0usize;
+
+    input.split(move |c: char| match c {
+        '(' => {
+            depth += 1;
+            false
+        }
+        ')' => {
+            depth = depth.saturating_sub(1);
+            false
+        }
+        ',' => depth == 0,
+        _ => false,
+    })
+}
+
+/// Split a style spec into its whitespace-separated tokens, ignoring whitespace nested inside
+/// `rgb(...)` calls (e.g. the spaces in `rgb(255, 136, 0)`).
+fn split_spec_tokens(input: &str) -> impl Iterator<Item = &str> {
+    let mut depth = 0usize;
+
+    input
+        .split(move |c: char| match c {
+            '(' => {
+                depth += 1;
+                false
+            }
+            ')' => {
+                depth = depth.saturating_sub(1);
+                false
+            }
+            c if c.is_whitespace() => depth == 0,
+            _ => false,
+        })
+        .filter(|token| !token.is_empty())
+}
+
+/// Parse a 3- or 6-digit hex color such as `f80` or `ff8800` into its RGB components.
+fn parse_hex_color(hex: &str) -> Option<(u8, u8, u8)> {
+    if !hex.is_ascii() {
+        return None;
+    }
+
+    let expand = |digit: char| digit.to_digit(16).map(|d| (d * 17) as u8);
+
+    match hex.len() {
+        3 => {
+            let mut digits = hex.chars();
+            Some((
+                expand(digits.next()?)?,
+                expand(digits.next()?)?,
+                expand(digits.next()?)?,
+            ))
+        }
+        6 => Some((
+            u8::from_str_radix(&hex[0..2], 16).ok()?,
+            u8::from_str_radix(&hex[2..4], 16).ok()?,
+            u8::from_str_radix(&hex[4..6], 16).ok()?,
+        )),
+        _ => None,
+    }
+}
+
+/// Parse the inside of an `rgb(r,g,b)` call into its components, validating that each one fits a
+/// `u8`.
+fn parse_rgb_color(inner: &str) -> Option<(u8, u8, u8)> {
+    let mut components = inner.split(',').map(str::trim);
+    let r = components.next()?.parse().ok()?;
+    let g = components.next()?.parse().ok()?;
+    let b = components.next()?.parse().ok()?;
+
+    if components.next().is_some() {
+        return None;
+    }
+
+    Some((r, g, b))
+}
+
+/// Parse style parts that aren't one of the fixed named colors or effects, namely hex colors
+/// (`fg:#ff8800`, `bg:#112233`), `rgb(...)`/`bg:rgb(...)` calls and 256-color xterm indices
+/// (`256:208`, `b:256:208`).
+fn parse_dynamic_style_part(style: Style, part: &str) -> Option<Style> {
+    if let Some(hex) = part.strip_prefix("fg:#") {
+        let (r, g, b) = parse_hex_color(hex)?;
+        return Some(style.truecolor(r, g, b));
+    }
+
+    if let Some(hex) = part.strip_prefix("bg:#") {
+        let (r, g, b) = parse_hex_color(hex)?;
+        return Some(style.on_truecolor(r, g, b));
+    }
+
+    if let Some(inner) = part
+        .strip_prefix("bg:rgb(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        let (r, g, b) = parse_rgb_color(inner)?;
+        return Some(style.on_truecolor(r, g, b));
+    }
+
+    if let Some(inner) = part
+        .strip_prefix("rgb(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        let (r, g, b) = parse_rgb_color(inner)?;
+        return Some(style.truecolor(r, g, b));
+    }
+
+    if let Some(index) = part.strip_prefix("b:256:") {
+        let index: u8 = index.parse().ok()?;
+        return Some(style.on_color(owo_colors::XtermColors::from(index)));
+    }
+
+    if let Some(index) = part.strip_prefix("256:") {
+        let index: u8 = index.parse().ok()?;
+        return Some(style.color(owo_colors::XtermColors::from(index)));
+    }
+
+    None
+}
+
 impl<'input> TryFrom<Description<'input>> for Style {
     type Error = String;
 
     fn try_from(value: Description<'input>) -> Result<Self, Self::Error> {
         let mut style = Style::new();
 
-        for part in value.0.split(',') {
-            match part.trim() {
-                "black" => style = style.black(),
-                "b:black" => style = style.on_black(),
-                "blue" => style = style.blue(),
-                "b:blue" => style = style.on_blue(),
-                "cyan" => style = style.cyan(),
-                "b:cyan" => style = style.on_cyan(),
-                "green" => style = style.green(),
-                "b:green" => style = style.on_green(),
-                "magenta" => style = style.magenta(),
-                "b:magenta" => style = style.on_magenta(),
-                "purple" => style = style.purple(),
-                "b:purple" => style = style.on_purple(),
-                "red" => style = style.red(),
-                "b:red" => style = style.on_red(),
-                "white" => style = style.white(),
-                "b:white" => style = style.on_white(),
-                "yellow" => style = style.yellow(),
-                "b:yellow" => style = style.on_yellow(),
-                "bold" => style = style.bold(),
-                "italic" => style = style.italic(),
-                "strike" => style = style.strikethrough(),
-                "underline" => style = style.underline(),
-                _ => return Err(format!("unknown style part '{}'", part.yellow().bold())),
-            }
+        for part in split_style_parts(value.0) {
+            let part = part.trim();
+
+            style = match part {
+                "black" => style.black(),
+                "b:black" => style.on_black(),
+                "blue" => style.blue(),
+                "b:blue" => style.on_blue(),
+                "cyan" => style.cyan(),
+                "b:cyan" => style.on_cyan(),
+                "green" => style.green(),
+                "b:green" => style.on_green(),
+                "magenta" => style.magenta(),
+                "b:magenta" => style.on_magenta(),
+                "purple" => style.purple(),
+                "b:purple" => style.on_purple(),
+                "red" => style.red(),
+                "b:red" => style.on_red(),
+                "white" => style.white(),
+                "b:white" => style.on_white(),
+                "yellow" => style.yellow(),
+                "b:yellow" => style.on_yellow(),
+                "bold" => style.bold(),
+                "italic" => style.italic(),
+                "strike" => style.strikethrough(),
+                "underline" => style.underline(),
+                _ => parse_dynamic_style_part(style, part)
+                    .ok_or_else(|| format!("unknown style part '{}'", part.yellow().bold()))?,
+            };
         }
 
         Ok(style)
     }
 }
 
+/// Token prefixes reserved for dynamic color descriptions (see `parse_dynamic_style_part`)
+/// rather than a capture group reference.
+const RESERVED_GROUP_PREFIXES: [&str; 4] = ["fg", "bg", "b", "256"];
+
+/// Parse a style specification into the overall match style and any per-capture-group overrides.
+///
+/// A specification is a whitespace-separated list of tokens. A token of the form `N:desc` or
+/// `name:desc`, where the part before the colon is a plain index or name (letters, digits and
+/// underscores only) and isn't one of [`RESERVED_GROUP_PREFIXES`], styles
+/// capture group `N`/`name` with `desc`; any other token contributes to the whole-match style,
+/// same as the original comma-separated color list.
+fn parse_style_spec(spec: &str) -> Result<(Style, Vec<(GroupRef, Style)>), String> {
+    let mut whole_match_tokens = Vec::new();
+    let mut group_styles = Vec::new();
+
+    for token in split_spec_tokens(spec) {
+        match token.split_once(':') {
+            Some((prefix, desc))
+                if !RESERVED_GROUP_PREFIXES.contains(&prefix)
+                    && prefix.chars().all(|c| c.is_alphanumeric() || c == '_') =>
+            {
+                let group_ref = match prefix.parse() {
+                    Ok(index) => GroupRef::Index(index),
+                    Err(_) => GroupRef::Name(prefix.to_string()),
+                };
+                let style = Description(desc).try_into()?;
+                group_styles.push((group_ref, style));
+            }
+            _ => whole_match_tokens.push(token),
+        }
+    }
+
+    let style = if whole_match_tokens.is_empty() {
+        Style::new()
+    } else {
+        Description(&whole_match_tokens.join(",")).try_into()?
+    };
+
+    Ok((style, group_styles))
+}
+
 fn read_line(buf: &mut String) -> Result<usize, String> {
     std::io::stdin()
         .read_line(buf)
@@ -221,7 +741,7 @@ fn try_main() -> Result<(), String> {
 
     if opts.help {
         println!(
-            "{}: <prog> | {} [--style <style>] [-m|--match <pattern> <description>] [-h|--help]",
+            "{}: <prog> | {} [--style <style>] [-m|--match <pattern> <description>] [-r|--replace <pattern> <template> <description>] [-t|--toggle <pattern> <description>] [-p|--pair <open> <close> <description>] [-h|--help]",
             "Usage".green().bold(),
             "cz".green().bold()
         );
@@ -229,7 +749,7 @@ fn try_main() -> Result<(), String> {
     }
 
     let mut buf = String::new();
-    let parser = Parser::new(&opts.styles);
+    let parser = Parser::new(&opts.styles, &opts.spans);
 
     while read_line(&mut buf)? > 0 {
         let line = &buf[..buf.len() - 1];
@@ -268,9 +788,11 @@ mod tests {
         let styles = &[MatchStyle {
             pattern,
             style: Style::new(),
+            template: None,
+            group_styles: Vec::new(),
         }];
 
-        let parser = Parser::new(styles);
+        let parser = Parser::new(styles, &[]);
         let mut regions = parser.regions("haystack");
 
         assert!(matches!(
@@ -287,9 +809,11 @@ mod tests {
         let styles = &[MatchStyle {
             pattern,
             style: Style::new(),
+            template: None,
+            group_styles: Vec::new(),
         }];
 
-        let parser = Parser::new(styles);
+        let parser = Parser::new(styles, &[]);
         let mut regions = parser.regions("a needle in the haystack");
 
         assert!(matches!(
@@ -299,7 +823,10 @@ mod tests {
 
         assert!(matches!(
             regions.next(),
-            Some(Region::Matched { text: "needle", .. })
+            Some(Region::Matched {
+                text: Cow::Borrowed("needle"),
+                ..
+            })
         ));
 
         assert!(matches!(
@@ -318,19 +845,26 @@ mod tests {
             MatchStyle {
                 pattern: Regex::new("foo").unwrap(),
                 style: Style::new(),
+                template: None,
+                group_styles: Vec::new(),
             },
             MatchStyle {
                 pattern: Regex::new("bar").unwrap(),
                 style: Style::new(),
+                template: None,
+                group_styles: Vec::new(),
             },
         ];
 
-        let parser = Parser::new(styles);
+        let parser = Parser::new(styles, &[]);
         let mut regions = parser.regions("foo bar");
 
         assert!(matches!(
             regions.next(),
-            Some(Region::Matched { text: "foo", .. })
+            Some(Region::Matched {
+                text: Cow::Borrowed("foo"),
+                ..
+            })
         ));
 
         assert!(matches!(
@@ -340,7 +874,379 @@ mod tests {
 
         assert!(matches!(
             regions.next(),
-            Some(Region::Matched { text: "bar", .. })
+            Some(Region::Matched {
+                text: Cow::Borrowed("bar"),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn replace_expands_capture_groups() {
+        let styles = &[MatchStyle {
+            pattern: Regex::new(r"(\w+)@(\w+)").unwrap(),
+            style: Style::new(),
+            template: Some("$2!$1".into()),
+            group_styles: Vec::new(),
+        }];
+
+        let parser = Parser::new(styles, &[]);
+        let mut regions = parser.regions("user@host");
+
+        assert!(matches!(
+            regions.next(),
+            Some(Region::Matched { text: Cow::Owned(ref text), .. }) if text == "host!user"
         ));
+
+        assert!(regions.next().is_none());
+    }
+
+    #[test]
+    fn replace_with_empty_expansion_is_not_styled() {
+        let styles = &[MatchStyle {
+            pattern: Regex::new(r"(\w+)@(\w+)").unwrap(),
+            style: Style::new(),
+            template: Some("$3".into()),
+            group_styles: Vec::new(),
+        }];
+
+        let parser = Parser::new(styles, &[]);
+        let mut regions = parser.regions("user@host");
+
+        assert!(matches!(
+            regions.next(),
+            Some(Region::Unmatched { text: "" })
+        ));
+
+        assert!(regions.next().is_none());
+    }
+
+    #[test]
+    fn per_capture_group_styles() {
+        let green = Style::new().green();
+        let yellow = Style::new().yellow();
+        let styles = &[MatchStyle {
+            pattern: Regex::new(r"(\w+)=(\w+)").unwrap(),
+            style: Style::new(),
+            template: None,
+            group_styles: vec![(GroupRef::Index(1), green), (GroupRef::Index(2), yellow)],
+        }];
+
+        let parser = Parser::new(styles, &[]);
+        let mut regions = parser.regions("key=value");
+
+        assert!(matches!(
+            regions.next(),
+            Some(Region::Matched {
+                text: Cow::Borrowed("key"),
+                ..
+            })
+        ));
+
+        assert!(matches!(
+            regions.next(),
+            Some(Region::Matched {
+                text: Cow::Borrowed("="),
+                ..
+            })
+        ));
+
+        assert!(matches!(
+            regions.next(),
+            Some(Region::Matched {
+                text: Cow::Borrowed("value"),
+                ..
+            })
+        ));
+
+        assert!(regions.next().is_none());
+    }
+
+    #[test]
+    fn named_capture_group_style_with_gap_fallback() {
+        let gray = Style::new();
+        let cyan = Style::new().cyan();
+        let white = Style::new().white();
+        let styles = &[MatchStyle {
+            pattern: Regex::new(r"(?P<k>\w+): (?P<v>.*)").unwrap(),
+            style: gray,
+            template: None,
+            group_styles: vec![
+                (GroupRef::Name("k".into()), cyan),
+                (GroupRef::Name("v".into()), white),
+            ],
+        }];
+
+        let parser = Parser::new(styles, &[]);
+        let mut regions = parser.regions("name: value");
+
+        assert!(matches!(
+            regions.next(),
+            Some(Region::Matched {
+                text: Cow::Borrowed("name"),
+                ..
+            })
+        ));
+
+        assert!(matches!(
+            regions.next(),
+            Some(Region::Matched {
+                text: Cow::Borrowed(": "),
+                ..
+            })
+        ));
+
+        assert!(matches!(
+            regions.next(),
+            Some(Region::Matched {
+                text: Cow::Borrowed("value"),
+                ..
+            })
+        ));
+
+        assert!(regions.next().is_none());
+    }
+
+    #[test]
+    fn parse_style_spec_splits_group_and_whole_match_tokens() {
+        let (style, group_styles) = parse_style_spec("1:green 2:yellow bold").unwrap();
+
+        assert_eq!(group_styles.len(), 2);
+        assert!(matches!(group_styles[0].0, GroupRef::Index(1)));
+        assert!(matches!(group_styles[1].0, GroupRef::Index(2)));
+        assert_eq!(
+            format!("{}", style.style("x")),
+            format!("{}", Style::new().bold().style("x"))
+        );
+    }
+
+    #[test]
+    fn parse_style_spec_spaced_rgb_call() {
+        let (style, group_styles) = parse_style_spec("rgb(255, 136, 0)").unwrap();
+        let expected = Style::new().truecolor(255, 136, 0);
+
+        assert!(group_styles.is_empty());
+        assert_eq!(
+            format!("{}", style.style("x")),
+            format!("{}", expected.style("x"))
+        );
+    }
+
+    #[test]
+    fn parse_style_spec_group_with_spaced_rgb_call() {
+        let (_, group_styles) = parse_style_spec("1:rgb(255, 136, 0)").unwrap();
+
+        assert_eq!(group_styles.len(), 1);
+        assert!(matches!(group_styles[0].0, GroupRef::Index(1)));
+        assert_eq!(
+            format!("{}", group_styles[0].1.style("x")),
+            format!("{}", Style::new().truecolor(255, 136, 0).style("x"))
+        );
+    }
+
+    #[test]
+    fn parse_style_spec_named_group() {
+        let (_, group_styles) = parse_style_spec("k:cyan v:white").unwrap();
+
+        assert_eq!(group_styles.len(), 2);
+        assert!(matches!(&group_styles[0].0, GroupRef::Name(name) if name == "k"));
+        assert!(matches!(&group_styles[1].0, GroupRef::Name(name) if name == "v"));
+    }
+
+    #[test]
+    fn hex_foreground_and_background() {
+        let style: Style = Description("fg:#ff8800,bg:#123").try_into().unwrap();
+        let expected = Style::new()
+            .truecolor(0xff, 0x88, 0x00)
+            .on_truecolor(0x11, 0x22, 0x33);
+
+        assert_eq!(
+            format!("{}", style.style("x")),
+            format!("{}", expected.style("x"))
+        );
+    }
+
+    #[test]
+    fn rgb_foreground_and_background() {
+        let style: Style = Description("rgb(255, 136, 0),bg:rgb(1,2,3)")
+            .try_into()
+            .unwrap();
+        let expected = Style::new().truecolor(255, 136, 0).on_truecolor(1, 2, 3);
+
+        assert_eq!(
+            format!("{}", style.style("x")),
+            format!("{}", expected.style("x"))
+        );
+    }
+
+    #[test]
+    fn xterm_256_foreground_and_background() {
+        let style: Style = Description("256:208,b:256:22").try_into().unwrap();
+        let expected = Style::new()
+            .color(owo_colors::XtermColors::from(208))
+            .on_color(owo_colors::XtermColors::from(22));
+
+        assert_eq!(
+            format!("{}", style.style("x")),
+            format!("{}", expected.style("x"))
+        );
+    }
+
+    #[test]
+    fn unknown_dynamic_style_part() {
+        assert!(Style::try_from(Description("fg:#zzzzzz")).is_err());
+        assert!(Style::try_from(Description("rgb(1,2)")).is_err());
+        assert!(Style::try_from(Description("256:nope")).is_err());
+    }
+
+    #[test]
+    fn non_ascii_hex_color_is_rejected_not_a_panic() {
+        assert!(Style::try_from(Description("fg:#€abc")).is_err());
+    }
+
+    #[test]
+    fn toggle_styles_text_between_markers_within_a_line() {
+        let spans = &[SpanStyle {
+            kind: SpanKind::Toggle(Regex::new(r"\*").unwrap()),
+            style: Style::new().bold(),
+        }];
+
+        let parser = Parser::new(&[], spans);
+        let mut regions = parser.regions("a *b* c");
+
+        assert!(matches!(
+            regions.next(),
+            Some(Region::Unmatched { text: "a " })
+        ));
+
+        assert!(matches!(
+            regions.next(),
+            Some(Region::Matched {
+                text: Cow::Borrowed("*b*"),
+                ..
+            })
+        ));
+
+        assert!(matches!(
+            regions.next(),
+            Some(Region::Unmatched { text: " c" })
+        ));
+
+        assert!(regions.next().is_none());
+    }
+
+    #[test]
+    fn pair_stays_open_across_lines() {
+        let spans = &[SpanStyle {
+            kind: SpanKind::Pair {
+                open: Regex::new(r"```").unwrap(),
+                close: Regex::new(r"```").unwrap(),
+            },
+            style: Style::new().green(),
+        }];
+
+        let parser = Parser::new(&[], spans);
+
+        let mut regions = parser.regions("before ```code");
+
+        assert!(matches!(
+            regions.next(),
+            Some(Region::Unmatched { text: "before " })
+        ));
+
+        assert!(matches!(
+            regions.next(),
+            Some(Region::Matched {
+                text: Cow::Borrowed("```code"),
+                ..
+            })
+        ));
+
+        assert!(regions.next().is_none());
+
+        let mut regions = parser.regions("more code```after");
+
+        assert!(matches!(
+            regions.next(),
+            Some(Region::Matched {
+                text: Cow::Borrowed("more code```"),
+                ..
+            })
+        ));
+
+        assert!(matches!(
+            regions.next(),
+            Some(Region::Unmatched { text: "after" })
+        ));
+
+        assert!(regions.next().is_none());
+    }
+
+    #[test]
+    fn unterminated_pair_styles_to_end_of_input() {
+        let spans = &[SpanStyle {
+            kind: SpanKind::Pair {
+                open: Regex::new(r"<b>").unwrap(),
+                close: Regex::new(r"</b>").unwrap(),
+            },
+            style: Style::new().bold(),
+        }];
+
+        let parser = Parser::new(&[], spans);
+        let mut regions = parser.regions("go <b>bold forever");
+
+        assert!(matches!(
+            regions.next(),
+            Some(Region::Unmatched { text: "go " })
+        ));
+
+        assert!(matches!(
+            regions.next(),
+            Some(Region::Matched {
+                text: Cow::Borrowed("<b>bold forever"),
+                ..
+            })
+        ));
+
+        assert!(regions.next().is_none());
+    }
+
+    /// `MatchStyles::find_match` narrows candidates with a `RegexSet` before running `captures`.
+    /// This re-implements the old brute-force scan (run every pattern's `captures` and keep the
+    /// earliest) and checks both approaches agree, including on ties broken by declaration order.
+    #[test]
+    fn regex_set_narrowing_agrees_with_brute_force_scan() {
+        let styles: Vec<MatchStyle> = (0..40)
+            .map(|index| MatchStyle {
+                pattern: Regex::new(&format!("tag{index}")).unwrap(),
+                style: Style::new(),
+                template: None,
+                group_styles: Vec::new(),
+            })
+            .collect();
+
+        let inputs = [
+            "nothing matches here",
+            "right at the start tag7 then more text",
+            "tag39 and tag2 both appear, tag2 is earlier though",
+            "tag0tag1tag2 all glued together",
+            "",
+        ];
+
+        let match_styles = MatchStyles::new(&styles);
+
+        for input in inputs {
+            let via_set = match_styles
+                .find_match(input)
+                .map(|(captures, _)| captures.get(0).unwrap().range());
+
+            let via_brute_force = styles
+                .iter()
+                .filter_map(|style| style.pattern.captures(input))
+                .min_by_key(|captures| captures.get(0).unwrap().start())
+                .map(|captures| captures.get(0).unwrap().range());
+
+            assert_eq!(via_set, via_brute_force, "mismatch for input {input:?}");
+        }
     }
 }
@@ -8,14 +8,20 @@ pub fn diff() -> Vec<MatchStyle> {
         MatchStyle {
             pattern: Regex::new(r"^\+.*$").unwrap(),
             style: Style::new().bright_green(),
+            template: None,
+            group_styles: Vec::new(),
         },
         MatchStyle {
             pattern: Regex::new(r"^\-.*$").unwrap(),
             style: Style::new().bright_red(),
+            template: None,
+            group_styles: Vec::new(),
         },
         MatchStyle {
             pattern: Regex::new(r"^@@.*$").unwrap(),
             style: Style::new().yellow(),
+            template: None,
+            group_styles: Vec::new(),
         },
     ]
 }